@@ -1,111 +1,290 @@
 // a lexer turns a string into tokens
 
+use super::token::CommentKind;
+use super::token::LexError;
+use super::token::Position;
 use super::token::Token;
 use super::token::TokenKind as K;
+use super::unescape;
+use super::unescape::Mode as EscapeMode;
+
+// tracks the lexer's position in a string interpolation, innermost last.
+// `InInterp`'s count is the nesting depth of `{`/`}` pairs seen since the
+// `${`, so `${ foo(bar{}) }` knows which `}` actually closes it.
+#[derive(Clone)]
+enum Mode {
+    InString,
+    InInterp(usize),
+}
 
 #[derive(Clone)]
 pub struct Lexer<'a> {
     string: &'a str,
     index: usize,
+    pos: Position,
+    modes: Vec<Mode>,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(string: &'a str) -> Lexer<'a> {
-        Lexer { string, index: 0 }
+        Lexer {
+            string,
+            index: 0,
+            pos: Position::new(1, 1),
+            modes: Vec::new(),
+        }
     }
 
     fn peek_char(&self) -> Option<char> {
         self.string[self.index..].chars().next()
     }
 
+    fn peek_second_char(&self) -> Option<char> {
+        let mut chars = self.string[self.index..].chars();
+        chars.next()?;
+        chars.next()
+    }
+
     fn next_char(&mut self) {
         let next = self.peek_char().unwrap();
         self.index += next.len_utf8();
+
+        if next == '\n' {
+            self.pos.line += 1;
+            self.pos.column = 1;
+        } else {
+            self.pos.column += 1;
+        }
+    }
+
+    // packages up everything consumed so far (since `start`) as an invalid
+    // token; callers are responsible for only consuming the offending span
+    // so the next call to `next` can keep lexing the rest of the source
+    fn invalid(&self, start: usize, start_pos: Position, reason: LexError) -> Token {
+        Token::new(K::Invalid(reason), start, self.index, start_pos, self.pos)
     }
 
-    fn invalid(&self, start: usize) -> Token {
-        Token::new(K::Invalid, start, self.string.len())
+    fn single(&mut self, kind: K) -> Token {
+        let start = self.index;
+        let start_pos = self.pos;
+        self.next_char();
+        Token::new(kind, start, self.index, start_pos, self.pos)
     }
 
     pub fn next(&mut self) -> Token {
+        if self.peek_char().is_none() && !self.modes.is_empty() {
+            return self.unterminated_nested();
+        }
+
+        match self.modes.last() {
+            Some(Mode::InString) => self.next_in_string(),
+            Some(Mode::InInterp(_)) => self.next_in_interp(),
+            None => self.next_code(),
+        }
+    }
+
+    // lexes a single token of ordinary code; used both at the top level and
+    // for the tokens inside a `${ ... }` interpolation
+    fn next_code(&mut self) -> Token {
         let start = self.index;
+        let start_pos = self.pos;
+
         let peek = match self.peek_char() {
             Some(c) => c,
-            None => return Token::new(K::End, start, start),
+            None => return Token::new(K::End, start, start, start_pos, start_pos),
         };
 
-        let token = match peek {
-            '\n' => Token::new(K::LineBreak, start, start + 1),
-            '@' => Token::new(K::At, start, start + 1),
-            ',' => Token::new(K::Comma, start, start + 1),
-            '*' => Token::new(K::Times, start, start + 1),
-            '(' => Token::new(K::ParenL, start, start + 1),
-            ')' => Token::new(K::ParenR, start, start + 1),
-            '[' => Token::new(K::SquareL, start, start + 1),
-            ']' => Token::new(K::SquareR, start, start + 1),
-            '{' => Token::new(K::CurlyL, start, start + 1),
-            '}' => Token::new(K::CurlyR, start, start + 1),
-            '.' => Token::new(K::Dot, start, start + 1),
+        match peek {
+            '\n' => self.single(K::LineBreak),
+            '@' => self.single(K::At),
+            ',' => self.single(K::Comma),
+            '*' => self.single(K::Times),
+            '(' => self.single(K::ParenL),
+            ')' => self.single(K::ParenR),
+            '[' => self.single(K::SquareL),
+            ']' => self.single(K::SquareR),
+            '{' => self.single(K::CurlyL),
+            '}' => self.single(K::CurlyR),
+            '.' => self.single(K::Dot),
             ' ' | '\r' | '\t' => self.from_whitespace(),
             '-' => self.from_dash(),
             '=' => self.from_equals(),
             '+' => self.from_plus(),
             '/' => self.from_slash(),
             '\'' => self.from_single_quote(),
-            '"' => self.from_quote(),
+            '"' => self.start_string(),
             ':' => self.from_colon(),
             '0'..='9' => self.from_digit(),
             'A'..='Z' | 'a'..='z' | '_' => self.from_letter(),
-            _ => self.invalid(start),
+            _ => {
+                self.next_char();
+                self.invalid(start, start_pos, LexError::UnexpectedChar)
+            }
+        }
+    }
+
+    // an interpolated or plain string opens the same way: consume the `"`,
+    // enter `Mode::InString`, and let later calls to `next` produce the
+    // text/interpolation/closing-quote tokens that make it up
+    fn start_string(&mut self) -> Token {
+        let start = self.index;
+        let start_pos = self.pos;
+        self.next_char();
+        self.modes.push(Mode::InString);
+        Token::new(K::StringStart, start, self.index, start_pos, self.pos)
+    }
+
+    fn next_in_string(&mut self) -> Token {
+        let start = self.index;
+        let start_pos = self.pos;
+
+        match self.peek_char() {
+            Some('"') => {
+                self.next_char();
+                self.modes.pop();
+                Token::new(K::StringEnd, start, self.index, start_pos, self.pos)
+            }
+            Some('$') if self.peek_second_char() == Some('{') => {
+                self.next_char();
+                self.next_char();
+                self.modes.push(Mode::InInterp(0));
+                Token::new(K::InterpStart, start, self.index, start_pos, self.pos)
+            }
+            _ => {
+                loop {
+                    match self.peek_char() {
+                        Some('"') => break,
+                        Some('$') if self.peek_second_char() == Some('{') => break,
+                        Some('\\') => {
+                            self.next_char();
+                            self.skip_escape();
+                        }
+                        Some(_) => self.next_char(),
+                        None => break,
+                    }
+                }
+                let unescaped =
+                    unescape::unescape(&self.string[start..self.index], EscapeMode::Str);
+                Token::new(
+                    K::StringText(unescaped),
+                    start,
+                    self.index,
+                    start_pos,
+                    self.pos,
+                )
+            }
+        }
+    }
+
+    fn next_in_interp(&mut self) -> Token {
+        let depth = match self.modes.last() {
+            Some(Mode::InInterp(depth)) => *depth,
+            _ => unreachable!("next_in_interp called without an InInterp mode"),
         };
 
-        self.index = token.end;
-        token
+        match self.peek_char() {
+            Some('{') => {
+                if let Some(Mode::InInterp(depth)) = self.modes.last_mut() {
+                    *depth += 1;
+                }
+                self.next_code()
+            }
+            Some('}') if depth == 0 => {
+                let start = self.index;
+                let start_pos = self.pos;
+                self.next_char();
+                self.modes.pop();
+                Token::new(K::InterpEnd, start, self.index, start_pos, self.pos)
+            }
+            Some('}') => {
+                if let Some(Mode::InInterp(depth)) = self.modes.last_mut() {
+                    *depth -= 1;
+                }
+                self.next_code()
+            }
+            _ => self.next_code(),
+        }
+    }
+
+    // reaching the end of input while still inside a string or an
+    // interpolation means it was never closed; report that instead of
+    // silently handing back an `End` token as if everything were fine
+    fn unterminated_nested(&mut self) -> Token {
+        let reason = match self.modes.last() {
+            Some(Mode::InInterp(_)) => LexError::UnterminatedInterpolation,
+            _ => LexError::UnterminatedString,
+        };
+        self.modes.clear();
+        let pos = self.pos;
+        self.invalid(self.index, pos, reason)
     }
 
     fn from_whitespace(&mut self) -> Token {
         let start = self.index;
+        let start_pos = self.pos;
 
         while let Some(' ' | '\r' | '\t') = self.peek_char() {
             self.next_char();
         }
 
-        Token::new(K::WhiteSpace, start, self.index)
+        Token::new(K::WhiteSpace, start, self.index, start_pos, self.pos)
     }
 
     fn from_dash(&mut self) -> Token {
         let start = self.index;
+        let start_pos = self.pos;
         self.next_char();
 
-        match self.peek_char() {
-            Some('>') => Token::new(K::ThinArrow, start, self.index + 1),
-            _ => Token::new(K::Minus, start, self.index),
-        }
+        let kind = match self.peek_char() {
+            Some('>') => {
+                self.next_char();
+                K::ThinArrow
+            }
+            _ => K::Minus,
+        };
+
+        Token::new(kind, start, self.index, start_pos, self.pos)
     }
 
     fn from_equals(&mut self) -> Token {
         let start = self.index;
+        let start_pos = self.pos;
         self.next_char();
 
-        match self.peek_char() {
-            Some('>') => Token::new(K::ThickArrow, start, self.index + 1),
-            Some('=') => Token::new(K::DoubleEquals, start, self.index + 1),
-            _ => Token::new(K::Equals, start, self.index),
-        }
+        let kind = match self.peek_char() {
+            Some('>') => {
+                self.next_char();
+                K::ThickArrow
+            }
+            Some('=') => {
+                self.next_char();
+                K::DoubleEquals
+            }
+            _ => K::Equals,
+        };
+
+        Token::new(kind, start, self.index, start_pos, self.pos)
     }
 
     fn from_plus(&mut self) -> Token {
         let start = self.index;
+        let start_pos = self.pos;
         self.next_char();
 
-        match self.peek_char() {
-            Some('+') => Token::new(K::DoublePlus, start, self.index + 1),
-            _ => Token::new(K::Plus, start, self.index),
-        }
+        let kind = match self.peek_char() {
+            Some('+') => {
+                self.next_char();
+                K::DoublePlus
+            }
+            _ => K::Plus,
+        };
+
+        Token::new(kind, start, self.index, start_pos, self.pos)
     }
 
     fn from_slash(&mut self) -> Token {
         let start = self.index;
+        let start_pos = self.pos;
         self.next_char();
 
         match self.peek_char() {
@@ -117,73 +296,146 @@ impl<'a> Lexer<'a> {
                         _ => self.next_char(),
                     };
                 }
-                Token::new(K::Comment, start, self.index)
+                let kind = CommentKind::classify(&self.string[start..self.index]);
+                Token::new(K::Comment(kind), start, self.index, start_pos, self.pos)
             }
             Some('*') => {
-                // TODO: nested comment support
                 self.next_char();
+                let mut depth = 1;
                 loop {
                     match self.peek_char() {
-                        None => return self.invalid(start),
-                        Some(c) => {
+                        None => {
+                            return self.invalid(
+                                start,
+                                start_pos,
+                                LexError::UnterminatedBlockComment,
+                            )
+                        }
+                        Some('/') => {
+                            self.next_char();
+                            if self.peek_char() == Some('*') {
+                                self.next_char();
+                                depth += 1;
+                            }
+                        }
+                        Some('*') => {
                             self.next_char();
-                            if c == '*' && self.peek_char() == Some('/') {
-                                break;
+                            if self.peek_char() == Some('/') {
+                                self.next_char();
+                                depth -= 1;
+                                if depth == 0 {
+                                    break;
+                                }
                             }
                         }
+                        Some(_) => self.next_char(),
+                    }
+                }
+                let kind = CommentKind::classify(&self.string[start..self.index]);
+                Token::new(K::Comment(kind), start, self.index, start_pos, self.pos)
+            }
+            _ => Token::new(K::Div, start, self.index, start_pos, self.pos),
+        }
+    }
+
+    // consumes whatever follows a backslash inside a `Char`/`String`
+    // literal. this doesn't validate the escape, just skips far enough
+    // (e.g. past the braces of a `\u{...}`) that the lexer can still find
+    // the real closing quote; decoding and validation is the `unescape`
+    // module's job, not the lexer's
+    fn skip_escape(&mut self) {
+        match self.peek_char() {
+            Some('u') => {
+                self.next_char();
+                if self.peek_char() == Some('{') {
+                    self.next_char();
+                    while !matches!(self.peek_char(), Some('}') | None) {
+                        self.next_char();
+                    }
+                    if self.peek_char() == Some('}') {
+                        self.next_char();
+                    }
+                }
+            }
+            Some('x') => {
+                self.next_char();
+                for _ in 0..2 {
+                    if matches!(self.peek_char(), Some('0'..='9' | 'a'..='f' | 'A'..='F')) {
+                        self.next_char();
                     }
                 }
-                self.next_char(); // consume the ending slash
-                Token::new(K::Comment, start, self.index)
             }
-            _ => Token::new(K::Div, start, self.index),
+            Some(_) => self.next_char(),
+            None => {}
         }
     }
 
     fn from_single_quote(&mut self) -> Token {
         let start = self.index;
+        let start_pos = self.pos;
         self.next_char();
 
         match self.peek_char() {
-            // TODO: add escaping support (the single quote char)
-            Some('\'') | None => return self.invalid(start),
+            Some('\'') => {
+                self.next_char(); // closing quote right after the opening one
+                return self.invalid(start, start_pos, LexError::EmptyCharLiteral);
+            }
+            None => return self.invalid(start, start_pos, LexError::UnterminatedChar),
+            Some('\\') => {
+                self.next_char();
+                self.skip_escape();
+                // a malformed escape body (e.g. `\xZZ`, `\u41`) can leave
+                // `skip_escape` short of the closing quote; keep consuming
+                // so the literal still closes and `unescape` gets to report
+                // the real diagnostic below, instead of this degrading into
+                // a structural `UnterminatedChar`
+                while !matches!(self.peek_char(), Some('\'') | None) {
+                    if self.peek_char() == Some('\\') {
+                        self.next_char();
+                        self.skip_escape();
+                    } else {
+                        self.next_char();
+                    }
+                }
+            }
             _ => self.next_char(),
         }
 
         match self.peek_char() {
-            Some('\'') => Token::new(K::Char, start, self.index + 1),
-            _ => self.invalid(start),
+            Some('\'') => {
+                self.next_char();
+                let unescaped =
+                    unescape::unescape(&self.string[start + 1..self.index - 1], EscapeMode::Char);
+                Token::new(K::Char(unescaped), start, self.index, start_pos, self.pos)
+            }
+            _ => self.invalid(start, start_pos, LexError::UnterminatedChar),
         }
     }
 
-    fn from_quote(&mut self) -> Token {
+    fn from_colon(&mut self) -> Token {
         let start = self.index;
+        let start_pos = self.pos;
         self.next_char();
 
-        loop {
-            match self.peek_char() {
-                // TODO: add escaping support and interpolation
-                Some('"') => break,
-                Some(_) => self.next_char(),
-                None => return self.invalid(start),
+        let kind = match self.peek_char() {
+            Some(':') => {
+                self.next_char();
+                K::DoubleColon
             }
-        }
+            _ => K::Colon,
+        };
 
-        Token::new(K::String, start, self.index + 1)
+        Token::new(kind, start, self.index, start_pos, self.pos)
     }
 
-    fn from_colon(&mut self) -> Token {
+    fn from_digit(&mut self) -> Token {
         let start = self.index;
-        self.next_char();
+        let start_pos = self.pos;
 
-        match self.peek_char() {
-            Some(':') => Token::new(K::DoubleColon, start, self.index + 1),
-            _ => Token::new(K::Colon, start, self.index),
+        if let Some(token) = self.from_radix_digit(start, start_pos) {
+            return token;
         }
-    }
 
-    fn from_digit(&mut self) -> Token {
-        let start = self.index;
         self.next_char();
 
         let mut dot_index = None;
@@ -192,7 +444,7 @@ impl<'a> Lexer<'a> {
                 Some('0'..='9' | '_') => self.next_char(),
                 Some('.') => {
                     if dot_index.is_some() {
-                        return self.invalid(start);
+                        return self.invalid(start, start_pos, LexError::UnexpectedChar);
                     }
                     self.next_char();
                     dot_index = Some(self.index);
@@ -201,16 +453,91 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        match dot_index {
+        if let Some(i) = dot_index {
             // can't end with a dot
-            Some(i) if i == self.index => self.invalid(start),
-            Some(_) => Token::new(K::Float, start, self.index),
-            None => Token::new(K::Int, start, self.index),
+            if i == self.index {
+                return self.invalid(start, start_pos, LexError::UnexpectedChar);
+            }
+        }
+        let mut is_float = dot_index.is_some();
+
+        if matches!(self.peek_char(), Some('e' | 'E')) {
+            let checkpoint = self.clone();
+            self.next_char();
+            if matches!(self.peek_char(), Some('+' | '-')) {
+                self.next_char();
+            }
+
+            if matches!(self.peek_char(), Some('0'..='9')) {
+                is_float = true;
+                while matches!(self.peek_char(), Some('0'..='9' | '_')) {
+                    self.next_char();
+                }
+            } else {
+                // no digits after the `e`/`E` (and optional sign), so it
+                // wasn't an exponent after all; back out and let whatever
+                // comes next be its own token(s)
+                *self = checkpoint;
+            }
+        }
+
+        self.scan_suffix();
+
+        let kind = if is_float { K::Float } else { K::Int };
+        Token::new(kind, start, self.index, start_pos, self.pos)
+    }
+
+    // `0x`/`0o`/`0b` integer literals, with the appropriate digit class for
+    // each radix. Returns `None` (without consuming anything) if `self`
+    // isn't positioned at one of these prefixes, so the caller can fall
+    // back to decimal lexing.
+    fn from_radix_digit(&mut self, start: usize, start_pos: Position) -> Option<Token> {
+        if self.peek_char() != Some('0') {
+            return None;
+        }
+
+        let is_radix_digit: fn(char) -> bool = match self.peek_second_char() {
+            Some('x') => |c| c.is_ascii_hexdigit(),
+            Some('o') => |c| ('0'..='7').contains(&c),
+            Some('b') => |c| c == '0' || c == '1',
+            _ => return None,
+        };
+
+        self.next_char(); // '0'
+        self.next_char(); // x/o/b
+
+        let mut has_digit = false;
+        loop {
+            match self.peek_char() {
+                Some(c) if is_radix_digit(c) => {
+                    has_digit = true;
+                    self.next_char();
+                }
+                Some('_') => self.next_char(),
+                _ => break,
+            }
+        }
+
+        if !has_digit {
+            return Some(self.invalid(start, start_pos, LexError::UnexpectedChar));
+        }
+
+        self.scan_suffix();
+        Some(Token::new(K::Int, start, self.index, start_pos, self.pos))
+    }
+
+    // consumes a trailing type suffix like `i32`/`u8`/`f64`. the suffix
+    // isn't checked against the set of real suffixes here, just captured as
+    // part of the token's span for a later stage to interpret
+    fn scan_suffix(&mut self) {
+        while let Some('A'..='Z' | 'a'..='z' | '0'..='9' | '_') = self.peek_char() {
+            self.next_char();
         }
     }
 
     fn from_letter(&mut self) -> Token {
         let start = self.index;
+        let start_pos = self.pos;
 
         while let Some('A'..='Z' | 'a'..='z' | '_') = self.peek_char() {
             self.next_char();
@@ -226,6 +553,286 @@ impl<'a> Lexer<'a> {
             _ => K::Identifier,
         };
 
-        Token::new(kind, start, self.index)
+        Token::new(kind, start, self.index, start_pos, self.pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::token::CommentShape;
+    use super::super::token::DocStyle;
+    use super::unescape::EscapeError;
+    use super::*;
+
+    // lexes `src` to completion, including the trailing `End` token
+    fn kinds(src: &str) -> Vec<K> {
+        let mut lexer = Lexer::new(src);
+        let mut kinds = Vec::new();
+        loop {
+            let token = lexer.next();
+            let is_end = matches!(token.kind, K::End);
+            kinds.push(token.kind);
+            if is_end {
+                break;
+            }
+        }
+        kinds
+    }
+
+    #[test]
+    fn crlf_line_break_only_advances_the_line_once() {
+        // `\r` is ordinary whitespace, so `\r\n` is a `WhiteSpace` token
+        // followed by the `LineBreak` that actually starts line 2 - it must
+        // not be double-counted as two line breaks
+        let mut lexer = Lexer::new("a\r\nb");
+        let a = lexer.next();
+        assert_eq!(a.start_pos, Position::new(1, 1));
+        assert_eq!(a.end_pos, Position::new(1, 2));
+
+        let cr = lexer.next();
+        assert!(matches!(cr.kind, K::WhiteSpace));
+        assert_eq!(cr.end_pos, Position::new(1, 3));
+
+        let lf = lexer.next();
+        assert!(matches!(lf.kind, K::LineBreak));
+        assert_eq!(lf.end_pos, Position::new(2, 1));
+
+        let b = lexer.next();
+        assert_eq!(b.start_pos, Position::new(2, 1));
+        assert_eq!(b.end_pos, Position::new(2, 2));
+    }
+
+    #[test]
+    fn column_counts_chars_not_utf8_bytes() {
+        // `é` is 2 bytes but a single column, so `l` right after it must
+        // land on column 3, not column 4
+        let kinds = kinds("h\u{e9}llo"); // héllo, with é as an invalid char
+        let mut lexer = Lexer::new("h\u{e9}llo");
+        let h = lexer.next();
+        assert_eq!(h.end_pos, Position::new(1, 2));
+
+        let e = lexer.next();
+        assert!(matches!(e.kind, K::Invalid(LexError::UnexpectedChar)));
+        assert_eq!(e.start_pos, Position::new(1, 2));
+        assert_eq!(e.end_pos, Position::new(1, 3));
+
+        assert!(matches!(kinds[2], K::Identifier)); // llo
+    }
+
+    #[test]
+    fn invalid_tokens_still_let_lexing_continue() {
+        // a single bad byte shouldn't stop the rest of the source from
+        // lexing - the `Invalid` token only covers the offending span, and
+        // the next `next()` call picks back up right after it
+        let kinds = kinds("a`b");
+        assert!(matches!(kinds[0], K::Identifier)); // a
+        assert!(matches!(kinds[1], K::Invalid(LexError::UnexpectedChar))); // `
+        assert!(matches!(kinds[2], K::Identifier)); // b
+        assert!(matches!(kinds[3], K::End));
+    }
+
+    #[test]
+    fn unterminated_char_is_recoverable() {
+        // `'ab` only consumes up through the one char a `Char` literal
+        // expects (`'a`); the lexer recovers and keeps going, so the
+        // trailing `b` is lexed as its own token rather than being eaten
+        let kinds = kinds("'ab");
+        assert!(matches!(kinds[0], K::Invalid(LexError::UnterminatedChar)));
+        assert!(matches!(kinds[1], K::Identifier)); // b
+        assert!(matches!(kinds[2], K::End));
+    }
+
+    #[test]
+    fn empty_char_literal_is_its_own_lex_error() {
+        let kinds = kinds("''");
+        assert!(matches!(kinds[0], K::Invalid(LexError::EmptyCharLiteral)));
+        assert!(matches!(kinds[1], K::End));
+    }
+
+    #[test]
+    fn line_comments_are_classified_by_prefix() {
+        assert_eq!(
+            CommentKind::classify("///"),
+            CommentKind {
+                shape: CommentShape::Line,
+                doc: Some(DocStyle::Outer)
+            }
+        );
+        assert_eq!(
+            CommentKind::classify("//!"),
+            CommentKind {
+                shape: CommentShape::Line,
+                doc: Some(DocStyle::Inner)
+            }
+        );
+        // `////` is a plain comment, not a doc comment - a fourth `/` is
+        // how code comments out a line that's already a doc comment
+        assert_eq!(
+            CommentKind::classify("////"),
+            CommentKind {
+                shape: CommentShape::Line,
+                doc: None
+            }
+        );
+        assert_eq!(
+            CommentKind::classify("// plain"),
+            CommentKind {
+                shape: CommentShape::Line,
+                doc: None
+            }
+        );
+    }
+
+    #[test]
+    fn block_comments_are_classified_by_prefix() {
+        assert_eq!(
+            CommentKind::classify("/** outer */"),
+            CommentKind {
+                shape: CommentShape::Block,
+                doc: Some(DocStyle::Outer)
+            }
+        );
+        assert_eq!(
+            CommentKind::classify("/*! inner */"),
+            CommentKind {
+                shape: CommentShape::Block,
+                doc: Some(DocStyle::Inner)
+            }
+        );
+        // `/**/` and `/* */` are plain, not doc comments - classification
+        // looks past the closing `*/` that the `**` prefix rule would
+        // otherwise be fooled by
+        assert_eq!(
+            CommentKind::classify("/**/"),
+            CommentKind {
+                shape: CommentShape::Block,
+                doc: None
+            }
+        );
+        assert_eq!(
+            CommentKind::classify("/* plain */"),
+            CommentKind {
+                shape: CommentShape::Block,
+                doc: None
+            }
+        );
+    }
+
+    #[test]
+    fn nested_block_comment_is_a_single_token() {
+        let kinds = kinds("/* outer /* inner */ outer */");
+        assert!(matches!(kinds[0], K::Comment(_)));
+        assert!(matches!(kinds[1], K::End));
+    }
+
+    #[test]
+    fn unterminated_nested_block_comment_reports_at_eof() {
+        // the inner `/* ... */` closes, but the outer one never does, so
+        // `depth` is still 1 when the string runs out
+        let kinds = kinds("/* outer /* inner */");
+        assert!(matches!(
+            kinds[0],
+            K::Invalid(LexError::UnterminatedBlockComment)
+        ));
+        assert!(matches!(kinds[1], K::End));
+    }
+
+    #[test]
+    fn interpolation_brace_depth_tracks_nested_curlies() {
+        // the `{`/`}` pair around `bar{}` must not be mistaken for the one
+        // that closes the `${ ... }` interpolation
+        let kinds = kinds(r#""${ foo(bar{}) }""#);
+        assert!(matches!(kinds[0], K::StringStart));
+        assert!(matches!(kinds[1], K::InterpStart));
+        assert!(matches!(kinds[2], K::WhiteSpace));
+        assert!(matches!(kinds[3], K::Identifier)); // foo
+        assert!(matches!(kinds[4], K::ParenL));
+        assert!(matches!(kinds[5], K::Identifier)); // bar
+        assert!(matches!(kinds[6], K::CurlyL));
+        assert!(matches!(kinds[7], K::CurlyR));
+        assert!(matches!(kinds[8], K::ParenR));
+        assert!(matches!(kinds[9], K::WhiteSpace));
+        assert!(matches!(kinds[10], K::InterpEnd));
+        assert!(matches!(kinds[11], K::StringEnd));
+        assert!(matches!(kinds[12], K::End));
+    }
+
+    #[test]
+    fn string_nested_inside_interpolation_has_its_own_mode() {
+        let kinds = kinds(r#""${"z"}""#);
+        assert!(matches!(kinds[0], K::StringStart)); // outer
+        assert!(matches!(kinds[1], K::InterpStart));
+        assert!(matches!(kinds[2], K::StringStart)); // inner
+        assert!(matches!(kinds[3], K::StringText(_)));
+        assert!(matches!(kinds[4], K::StringEnd)); // inner
+        assert!(matches!(kinds[5], K::InterpEnd));
+        assert!(matches!(kinds[6], K::StringEnd)); // outer
+        assert!(matches!(kinds[7], K::End));
+    }
+
+    #[test]
+    fn radix_prefix_without_digits_is_invalid() {
+        for src in ["0x", "0o", "0b"] {
+            let kinds = kinds(src);
+            assert!(
+                matches!(kinds[0], K::Invalid(LexError::UnexpectedChar)),
+                "{src}"
+            );
+        }
+    }
+
+    #[test]
+    fn radix_prefix_with_only_separators_is_invalid() {
+        for src in ["0x_", "0o_", "0b_"] {
+            let kinds = kinds(src);
+            assert!(
+                matches!(kinds[0], K::Invalid(LexError::UnexpectedChar)),
+                "{src}"
+            );
+        }
+    }
+
+    #[test]
+    fn exponent_without_digits_backtracks_to_a_suffix() {
+        // `1e`/`1e+` aren't exponents after all, since nothing follows the
+        // `e`/sign; backtracking leaves the `e` to be picked up as a (bogus
+        // but lexable) suffix on the `1`, and the sign as its own token
+        let no_sign = kinds("1e");
+        assert!(matches!(no_sign[0], K::Int));
+        assert_eq!(no_sign.len(), 2); // Int, End
+        assert!(matches!(no_sign[1], K::End));
+
+        let with_sign = kinds("1e+");
+        assert!(matches!(with_sign[0], K::Int));
+        assert!(matches!(with_sign[1], K::Plus));
+        assert!(matches!(with_sign[2], K::End));
+    }
+
+    #[test]
+    fn valid_radix_and_exponent_literals() {
+        let hex = kinds("0x1Fu8");
+        assert!(matches!(hex[0], K::Int));
+        assert!(matches!(hex[1], K::End));
+
+        let exp = kinds("1e10");
+        assert!(matches!(exp[0], K::Float));
+        assert!(matches!(exp[1], K::End));
+
+        let exp_with_suffix = kinds("3.5e-3f64");
+        assert!(matches!(exp_with_suffix[0], K::Float));
+        assert!(matches!(exp_with_suffix[1], K::End));
+    }
+
+    #[test]
+    fn malformed_char_escape_still_closes_and_reports_via_unescape() {
+        // `\xZZ` isn't a valid hex escape, but the literal should still
+        // close at the next `'` and let `unescape` report the diagnostic,
+        // instead of degrading into a structural `UnterminatedChar`
+        let kinds = kinds(r"'\xZZ'");
+        match &kinds[0] {
+            K::Char(u) => assert_eq!(u.errors, vec![(0, 3, EscapeError::BadHexDigit)]),
+            other => panic!("expected Char, got {other:?}"),
+        }
+        assert!(matches!(kinds[1], K::End));
     }
 }
@@ -0,0 +1,3 @@
+pub mod lexer;
+pub mod token;
+pub mod unescape;
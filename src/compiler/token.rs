@@ -1,10 +1,12 @@
+use super::unescape::Unescaped;
+
 #[derive(Debug, Clone)]
 pub enum TokenKind {
-    Invalid,
+    Invalid(LexError),
     End,
     WhiteSpace,
     LineBreak,
-    Comment,
+    Comment(CommentKind),
     Identifier,
     Struct,
     Union,
@@ -32,21 +34,117 @@ pub enum TokenKind {
     Minus,
     Times,
     Div,
-    String,
-    Char,
+    StringStart,
+    StringText(Unescaped),
+    StringEnd,
+    InterpStart,
+    InterpEnd,
+    Char(Unescaped),
     Int,
     Float,
 }
 
+// a reason the lexer could not produce a valid token, kept on the token
+// itself so a single bad span never stops the rest of the file from lexing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LexError {
+    UnexpectedChar,
+    UnterminatedString,
+    UnterminatedChar,
+    UnterminatedBlockComment,
+    UnterminatedInterpolation,
+    EmptyCharLiteral,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentShape {
+    Line,
+    Block,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocStyle {
+    Inner,
+    Outer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommentKind {
+    pub shape: CommentShape,
+    pub doc: Option<DocStyle>,
+}
+
+impl CommentKind {
+    // classifies a comment from its full source text (including the
+    // leading `//`/`/*`), following the same prefix rules as rustdoc:
+    // `///`/`/**` are outer doc comments, `//!`/`/*!` are inner doc
+    // comments, and everything else (including `////` and `/**/`) is
+    // an ordinary, non-doc comment
+    pub fn classify(text: &str) -> CommentKind {
+        if let Some(rest) = text.strip_prefix("//") {
+            let doc = match rest.as_bytes() {
+                [b'/', b'/', ..] => None,
+                [b'/', ..] => Some(DocStyle::Outer),
+                [b'!', ..] => Some(DocStyle::Inner),
+                _ => None,
+            };
+            CommentKind {
+                shape: CommentShape::Line,
+                doc,
+            }
+        } else {
+            let rest = text.strip_prefix("/*").unwrap_or(text);
+            let doc = match rest.as_bytes() {
+                [b'*', b'/', ..] => None,
+                [b'*', b'*', ..] => None,
+                [b'*', ..] => Some(DocStyle::Outer),
+                [b'!', ..] => Some(DocStyle::Inner),
+                _ => None,
+            };
+            CommentKind {
+                shape: CommentShape::Block,
+                doc,
+            }
+        }
+    }
+}
+
+// 1-indexed, so the first character of a file is line 1, column 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Position {
+        Position { line, column }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Token {
     pub kind: TokenKind,
     pub start: usize,
     pub end: usize,
+    pub start_pos: Position,
+    pub end_pos: Position,
 }
 
 impl Token {
-    pub fn new(kind: TokenKind, start: usize, end: usize) -> Token {
-        Token { kind, start, end }
+    pub fn new(
+        kind: TokenKind,
+        start: usize,
+        end: usize,
+        start_pos: Position,
+        end_pos: Position,
+    ) -> Token {
+        Token {
+            kind,
+            start,
+            end,
+            start_pos,
+            end_pos,
+        }
     }
 }
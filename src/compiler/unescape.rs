@@ -0,0 +1,220 @@
+// decodes the escape sequences inside `Char`/`String` token text. this is
+// kept separate from the lexer: the lexer only needs to know where a
+// literal ends (so it just skips over `\X` without judging whether `X` is
+// a real escape), while this module does the actual decoding and reports
+// anything malformed.
+
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+type Chars<'a> = Peekable<CharIndices<'a>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Char,
+    Str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscapeError {
+    UnknownEscape,
+    BadHexDigit,
+    CodePointOutOfRange,
+    LoneSurrogate,
+    EmptyUnicodeEscape,
+    UnterminatedUnicodeEscape,
+}
+
+#[derive(Debug, Clone)]
+pub struct Unescaped {
+    pub mode: Mode,
+    pub value: String,
+    // spans are byte offsets into the `text` passed to `unescape`
+    pub errors: Vec<(usize, usize, EscapeError)>,
+}
+
+// `text` is the raw literal contents, with the surrounding quotes already
+// stripped off by the caller
+pub fn unescape(text: &str, mode: Mode) -> Unescaped {
+    let mut value = String::new();
+    let mut errors = Vec::new();
+    let mut chars: Chars = text.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some((_, 'n')) => value.push('\n'),
+            Some((_, 'r')) => value.push('\r'),
+            Some((_, 't')) => value.push('\t'),
+            Some((_, '\\')) => value.push('\\'),
+            Some((_, '\'')) => value.push('\''),
+            Some((_, '"')) => value.push('"'),
+            Some((_, '$')) => value.push('$'),
+            Some((_, '0')) => value.push('\0'),
+            Some((_, 'x')) => match unescape_hex(&mut chars, text.len()) {
+                Ok(c) => value.push(c),
+                Err((end, e)) => errors.push((start, end, e)),
+            },
+            Some((_, 'u')) => match unescape_unicode(&mut chars, text.len()) {
+                Ok(c) => value.push(c),
+                Err((end, e)) => errors.push((start, end, e)),
+            },
+            Some((i, other)) => {
+                value.push(other);
+                errors.push((start, i + other.len_utf8(), EscapeError::UnknownEscape));
+            }
+            None => errors.push((start, text.len(), EscapeError::UnknownEscape)),
+        }
+    }
+
+    Unescaped {
+        mode,
+        value,
+        errors,
+    }
+}
+
+// `\xNN`: exactly two hex digits, restricted to the ASCII range like rustc
+fn unescape_hex(chars: &mut Chars, end_of_text: usize) -> Result<char, (usize, EscapeError)> {
+    let mut value: u32 = 0;
+    let mut end = end_of_text;
+
+    for _ in 0..2 {
+        match chars.next() {
+            Some((i, d)) if d.is_ascii_hexdigit() => {
+                value = value * 16 + d.to_digit(16).unwrap();
+                end = i + d.len_utf8();
+            }
+            Some((i, c)) => return Err((i + c.len_utf8(), EscapeError::BadHexDigit)),
+            None => return Err((end, EscapeError::BadHexDigit)),
+        }
+    }
+
+    if value > 0x7f {
+        return Err((end, EscapeError::CodePointOutOfRange));
+    }
+    Ok(value as u8 as char)
+}
+
+// `\u{...}`: one to six hex digits inside braces, naming any Unicode scalar
+fn unescape_unicode(chars: &mut Chars, end_of_text: usize) -> Result<char, (usize, EscapeError)> {
+    match chars.next() {
+        Some((_, '{')) => {}
+        Some((i, c)) => return Err((i + c.len_utf8(), EscapeError::UnterminatedUnicodeEscape)),
+        None => return Err((end_of_text, EscapeError::UnterminatedUnicodeEscape)),
+    }
+
+    let mut value: u32 = 0;
+    let mut digits = 0;
+    let mut end = end_of_text;
+
+    loop {
+        match chars.peek() {
+            Some((i, '}')) => {
+                end = i + 1;
+                chars.next();
+                break;
+            }
+            Some((i, d)) if d.is_ascii_hexdigit() => {
+                let (i, d) = (*i, *d);
+                value = value.saturating_mul(16).saturating_add(d.to_digit(16).unwrap());
+                digits += 1;
+                end = i + d.len_utf8();
+                chars.next();
+            }
+            Some((i, c)) => return Err((i + c.len_utf8(), EscapeError::BadHexDigit)),
+            None => return Err((end, EscapeError::UnterminatedUnicodeEscape)),
+        }
+    }
+
+    if digits == 0 {
+        return Err((end, EscapeError::EmptyUnicodeEscape));
+    }
+    if digits > 6 {
+        return Err((end, EscapeError::CodePointOutOfRange));
+    }
+
+    match char::from_u32(value) {
+        Some(c) => Ok(c),
+        None if (0xd800..=0xdfff).contains(&value) => Err((end, EscapeError::LoneSurrogate)),
+        None => Err((end, EscapeError::CodePointOutOfRange)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_text_is_unchanged() {
+        let u = unescape("hello", Mode::Str);
+        assert_eq!(u.value, "hello");
+        assert!(u.errors.is_empty());
+    }
+
+    #[test]
+    fn simple_escapes() {
+        let u = unescape("a\\nb\\tc\\\\d\\\"e", Mode::Str);
+        assert_eq!(u.value, "a\nb\tc\\d\"e");
+        assert!(u.errors.is_empty());
+    }
+
+    #[test]
+    fn hex_escape() {
+        let u = unescape(r"\x41", Mode::Char);
+        assert_eq!(u.value, "A");
+        assert!(u.errors.is_empty());
+    }
+
+    #[test]
+    fn unicode_escape() {
+        let u = unescape(r"\u{1F600}", Mode::Char);
+        assert_eq!(u.value, "\u{1F600}");
+        assert!(u.errors.is_empty());
+    }
+
+    #[test]
+    fn bad_hex_digit_span_covers_the_offending_character() {
+        // `\xZZ`: the `Z` right after `\x` is where the hex digit was
+        // expected, so the error span must include it, not stop short
+        let u = unescape(r"\xZZ", Mode::Str);
+        assert_eq!(u.errors, vec![(0, 3, EscapeError::BadHexDigit)]);
+    }
+
+    #[test]
+    fn bad_unicode_digit_span_covers_the_offending_character() {
+        let u = unescape(r"\u{1Z}", Mode::Str);
+        assert_eq!(u.errors, vec![(0, 5, EscapeError::BadHexDigit)]);
+    }
+
+    #[test]
+    fn unterminated_unicode_escape_span_covers_the_offending_character() {
+        // missing the opening `{`: the error should point past whatever
+        // character was found instead of it
+        let u = unescape(r"\uZ", Mode::Str);
+        assert_eq!(
+            u.errors,
+            vec![(0, 3, EscapeError::UnterminatedUnicodeEscape)]
+        );
+    }
+
+    #[test]
+    fn unknown_escape_is_reported() {
+        let u = unescape(r"\q", Mode::Str);
+        assert_eq!(u.value, "q");
+        assert_eq!(u.errors, vec![(0, 2, EscapeError::UnknownEscape)]);
+    }
+
+    #[test]
+    fn dollar_escape_is_a_literal_dollar_sign() {
+        // `\$` is how a string writes a literal `${` without it being read
+        // as the start of an interpolation
+        let u = unescape(r"\${not interp}", Mode::Str);
+        assert_eq!(u.value, "${not interp}");
+        assert!(u.errors.is_empty());
+    }
+}